@@ -20,72 +20,216 @@ fn get_parent(left: &blake3::Output, right: &blake3::Output) -> blake3::Output {
     parent_output
 }
 
+// The parent of a node is always at node_index / 2
+fn get_parent_index(index: usize) -> usize {
+    index >> 1
+}
+
+fn get_sibling_index(index: usize) -> usize {
+    // Bit-wise XOR to get the sibling index
+    // Example: Sibling of index 4(0b100) is 5(0b101) and sibling of index 5(0b101) is 4(0b100)
+    index ^ 1
+}
+
+fn is_left(index: usize) -> bool {
+    // All left-children have an even node index
+    index % 2 == 0
+}
+
+/// Given an index of the current node, identify its direct sibling,
+/// identify which node is left, which is right, and return them.
+fn get_left_and_right_node_indices_from_index(current_index: usize) -> (usize, usize) {
+    let sibling_index = get_sibling_index(current_index);
+
+    // Use boolean indexing to avoid if statement branching
+    let node_pair = [current_index, sibling_index]; // Stack allocation
+
+    // If the sibling is the left child, is_left returns 1 and gets the sibling
+    // If the sibling is the right child, is_left returns 0 and gets the node to update (the left child)
+    let left_node_index = node_pair[is_left(sibling_index) as usize];
+
+    // If the node to update is the left child, is_left returns 1 and gets the sibling (the right child)
+    // If the node to update is the right child, is_left returns 0 and gets the node to update
+    let right_node_index = node_pair[is_left(current_index) as usize];
+
+    (left_node_index, right_node_index)
+}
+
+/// Backing storage for merkle tree nodes, addressed by the tree's 1-indexed node numbering.
+/// `BinaryMerkleTree` is generic over this trait so it can be backed by something other than
+/// an in-memory `Vec` (e.g. a key-value store), letting the tree be persisted across restarts.
+pub trait NodeStore {
+    fn read(&self, index: usize) -> blake3::Output;
+    fn set(&mut self, index: usize, value: blake3::Output);
+
+    /// Set several nodes at once. The default implementation just calls `set` in a loop;
+    /// backends that can batch writes efficiently should override this.
+    fn batch_set(&mut self, updates: impl IntoIterator<Item = (usize, blake3::Output)>) {
+        for (index, value) in updates {
+            self.set(index, value);
+        }
+    }
+}
+
+impl NodeStore for Vec<blake3::Output> {
+    // Named `read`, not `get`: `Vec` already has an inherent `get` (`-> Option<&T>` via
+    // `Deref<Target = [T]>`), and a trait method of the same name found at the `Vec<T>` deref
+    // step shadows it instead of losing to it, so plain `Vec<blake3::Output>` values (like
+    // `version_roots`) would silently stop being able to call the real `Vec::get`.
+    fn read(&self, index: usize) -> blake3::Output {
+        self[index]
+    }
+
+    fn set(&mut self, index: usize, value: blake3::Output) {
+        if index >= self.len() {
+            self.resize(index + 1, blake3::Output::new([0; 16], 0));
+        }
+        self[index] = value;
+    }
+}
+
 /// Binary merkle tree that is 1-indexed and is constructed out of leaves equal to a power of two.
 /// If the number of leaves is not a power of two, add zero nodes until the number of leaves is a power of two.
+/// Nodes are read and written through `S`, which defaults to an in-memory `Vec`.
 #[derive(Debug, Clone)]
-pub struct BinaryMerkleTree {
-    pub tree: Vec<blake3::Output>,
+pub struct BinaryMerkleTree<S: NodeStore = Vec<blake3::Output>> {
+    pub tree: S,
+    /// One past the highest leaf slot assigned so far; `append_leaf` writes to this slot.
+    pub next_index: usize,
+    num_leaves: usize,
+    /// The root recorded after each batch committed by `bulk_insert_leaves`, indexed by version.
+    version_roots: Vec<blake3::Output>,
+    /// Node values superseded by a committed batch, tagged with the version that superseded them,
+    /// kept around so historical roots stay provable until `prune` drops old versions.
+    archived_nodes: Vec<(u64, usize, blake3::Output)>,
+    /// The highest `up_to_version` ever passed to `prune`. Versions below this may have archived
+    /// nodes missing, so `node_at_version` can't tell "never changed" from "pruned" without it.
+    pruned_before: u64,
 }
 
-impl BinaryMerkleTree {
-    pub fn new_from_leaves(leaves: Vec<blake3::Output>) -> BinaryMerkleTree {
+impl<S: NodeStore> BinaryMerkleTree<S> {
+    pub fn new_from_leaves(leaves: Vec<blake3::Output>) -> BinaryMerkleTree<S>
+    where
+        S: Default,
+    {
         // Initialize a zero vector with the correct number of nodes
+        let number_of_assigned_leaves = leaves.len();
         let number_of_leaves = leaves.len().next_power_of_two();
         let mut tree = Self::new_empty(number_of_leaves as u64);
 
         tree.create_tree_from_leaves(leaves);
+        // Leaves are written right-aligned, so the last leaf slot is the highest one assigned.
+        if number_of_assigned_leaves > 0 {
+            tree.next_index = number_of_leaves;
+        }
 
         tree
     }
 
     pub fn root(&self) -> blake3::Output {
-        self.tree[1]
+        self.tree.read(1)
     }
 
     pub fn num_leaves(&self) -> usize {
-        self.tree.len() / 2
+        self.num_leaves
     }
 
     pub fn get_tree_length(&self) -> usize {
-        self.tree.len() - 1 // Minus one because the tree is 1-indexed
+        2 * self.num_leaves - 1 // Minus one because the tree is 1-indexed
     }
 
-    pub fn new_empty(number_of_leaves: u64) -> Self {
-        assert!(number_of_leaves.is_power_of_two());
-        let tree: Vec<blake3::Output> =
-            vec![blake3::Output::new([0; 16], 0); 2 * number_of_leaves as usize]; // We don't subtract one because the tree is 1-indexed
-        BinaryMerkleTree { tree }
+    /// The version that will be recorded the next time `bulk_insert_leaves` commits a batch.
+    pub fn current_version(&self) -> u64 {
+        self.version_roots.len() as u64
     }
 
-    // The parent of a node is always at node_index / 2
-    fn get_parent_index(index: usize) -> usize {
-        index >> 1
+    /// The root recorded after the batch committed as `version`, or `None` if no batch has been
+    /// committed as `version` yet. `prune` never removes recorded roots.
+    pub fn root_at_version(&self, version: u64) -> Option<blake3::Output> {
+        self.version_roots.get(version as usize).copied()
+    }
+
+    /// Drop archived node values (leaves and interior nodes) superseded strictly before `up_to_version`: they are
+    /// no longer reachable from any root at `up_to_version` or later. Root history is untouched,
+    /// but `node_at_version`/`prove_at_version` can no longer answer for versions before
+    /// `up_to_version`, even for nodes that happened to never change.
+    pub fn prune(&mut self, up_to_version: u64) {
+        self.archived_nodes
+            .retain(|&(version, _, _)| version >= up_to_version);
+        self.pruned_before = self.pruned_before.max(up_to_version);
+    }
+
+    /// The value node `index` held immediately after the batch committed as `version`.
+    /// Returns `None` if that historical value is no longer reconstructable: either the node
+    /// was overwritten again and the archived value from that overwrite was pruned, or
+    /// `version` itself predates the last `prune` and nothing before it is retained at all.
+    pub fn node_at_version(&self, version: u64, index: usize) -> Option<blake3::Output> {
+        let next_overwrite = self
+            .archived_nodes
+            .iter()
+            .filter(|&&(archived_version, archived_index, _)| {
+                archived_index == index && archived_version > version
+            })
+            .min_by_key(|&&(archived_version, _, _)| archived_version);
+
+        match next_overwrite {
+            Some(&(_, _, old_value)) => Some(old_value),
+            None if version < self.pruned_before => None,
+            None => Some(self.tree.read(index)),
+        }
+    }
+
+    pub fn new_empty(number_of_leaves: u64) -> Self
+    where
+        S: Default,
+    {
+        assert!(number_of_leaves.is_power_of_two());
+        let num_leaves = number_of_leaves as usize;
+        let zero_node = blake3::Output::new([0; 16], 0);
+        let mut tree = S::default();
+        tree.batch_set((0..2 * num_leaves).map(|index| (index, zero_node)));
+
+        BinaryMerkleTree {
+            tree,
+            next_index: 0,
+            num_leaves,
+            version_roots: Vec::new(),
+            archived_nodes: Vec::new(),
+            pruned_before: 0,
+        }
     }
 
     fn create_tree_from_leaves(&mut self, leaves: Vec<blake3::Output>) {
         // Copy the leaves into the end of the tree
         let number_of_leaves = leaves.len();
-        self.tree
-            .splice(self.tree.capacity() - number_of_leaves.., leaves);
+        let leaf_write_start = 2 * self.num_leaves - number_of_leaves;
+        self.tree.batch_set(
+            leaves
+                .into_iter()
+                .enumerate()
+                .map(|(offset, leaf)| (leaf_write_start + offset, leaf)),
+        );
         // If there is only one leaf (plus the filler first node), the tree is simply that leaf
         if number_of_leaves == 1 {
             return;
         }
 
-        // Build ancestors
-        let leaf_start_index = self.get_tree_length() / 2 + 1;
-        let leaves_with_indices = self.tree[leaf_start_index..]
-            .iter()
-            .copied()
-            .zip(leaf_start_index..leaf_start_index + number_of_leaves);
+        // Build ancestors. Read the whole leaf region (`self.num_leaves()` wide, including any
+        // zero-filler padding), not just `number_of_leaves`: the leaves above were written
+        // right-aligned at `leaf_write_start`, so when `leaves` didn't already fill the capacity,
+        // stopping at `number_of_leaves` would read the wrong (left-aligned) slice and pair
+        // non-sibling nodes together.
+        let leaf_start_index = self.num_leaves();
+        let leaves_with_indices = (leaf_start_index..leaf_start_index + self.num_leaves())
+            .map(|index| (self.tree.read(index), index));
         let mut hash_queue = VecDeque::from_iter(leaves_with_indices);
         while hash_queue.len() > 1 {
             let (left_child, left_index) = hash_queue.pop_front().unwrap();
             let (right_child, _right_index) = hash_queue.pop_front().unwrap(); // There should always be another node in the queue
-            let parent_index = BinaryMerkleTree::get_parent_index(left_index);
+            let parent_index = get_parent_index(left_index);
 
             let parent_hash = get_parent(&left_child, &right_child);
-            self.tree[parent_index] = parent_hash;
+            self.tree.set(parent_index, parent_hash);
             hash_queue.push_back((parent_hash, parent_index));
         }
     }
@@ -94,24 +238,28 @@ impl BinaryMerkleTree {
     /// Leaf index input is 0-indexed where the first leaf is index 0
     /// Leaf_index input should be 0-indexed where the first leaf would be entered as index 0
     pub fn update_leaf(&mut self, leaf_index: usize, leaf: blake3::Output) {
+        if leaf_index + 1 > self.next_index {
+            self.next_index = leaf_index + 1;
+        }
+
         let real_leaf_index = leaf_index + self.num_leaves();
-        if self.tree[real_leaf_index].chaining_value() == leaf.chaining_value() {
+        if self.tree.read(real_leaf_index).chaining_value() == leaf.chaining_value() {
             return;
         }
-        self.tree[real_leaf_index] = leaf;
+        self.tree.set(real_leaf_index, leaf);
 
         let mut current_index = real_leaf_index;
         while current_index > 1 {
             // Update parent
-            let parent_index = BinaryMerkleTree::get_parent_index(current_index);
+            let parent_index = get_parent_index(current_index);
 
             let (left_node_index, right_node_index) =
-                self.get_left_and_right_node_indices_from_index(current_index);
-            let left_node = &self.tree[left_node_index];
-            let right_node = &self.tree[right_node_index];
+                get_left_and_right_node_indices_from_index(current_index);
+            let left_node = self.tree.read(left_node_index);
+            let right_node = self.tree.read(right_node_index);
 
-            let parent_hash = get_parent(left_node, right_node);
-            self.tree[parent_index] = parent_hash;
+            let parent_hash = get_parent(&left_node, &right_node);
+            self.tree.set(parent_index, parent_hash);
             current_index = parent_index;
         }
     }
@@ -119,6 +267,8 @@ impl BinaryMerkleTree {
     /// Bulk insert leaves and propogate hash updates to all ancestors.
     /// This method avoid updating shared parents if given two direct siblings to update.
     /// Leaf_index input should be 0-indexed where the first leaf would be entered as index 0
+    /// Superseded node values are archived and a new version recording the resulting root is
+    /// committed, so the batch can later be looked up with `root_at_version`.
     pub fn bulk_insert_leaves<I, J>(
         &mut self,
         leaf_indices_iter: I,
@@ -142,9 +292,20 @@ impl BinaryMerkleTree {
             return None;
         }
 
+        if let Some(&last_index) = leaf_indices.last() {
+            let assigned_up_to = last_index - leaf_offset + 1;
+            if assigned_up_to > self.next_index {
+                self.next_index = assigned_up_to;
+            }
+        }
+
+        let version = self.version_roots.len() as u64;
+
         // Insert all leaf nodes
         for (leaf_index, updated_leaf_hash) in leaf_indices.iter().zip(leaf_hashes_iter) {
-            self.tree[*leaf_index] = updated_leaf_hash;
+            let old_leaf = self.tree.read(*leaf_index);
+            self.archived_nodes.push((version, *leaf_index, old_leaf));
+            self.tree.set(*leaf_index, updated_leaf_hash);
         }
 
         // Update ancestors based on sorted leaf indices
@@ -157,7 +318,7 @@ impl BinaryMerkleTree {
 
             // If the next ancestor to update is the sibling's, pop it from the queue
             // since it will have the same parent as the current node
-            let sibling_index = BinaryMerkleTree::get_sibling_index(current_index);
+            let sibling_index = get_sibling_index(current_index);
             if let Some(&next_index) = update_queue.front() {
                 if next_index == sibling_index {
                     update_queue.pop_front();
@@ -165,47 +326,521 @@ impl BinaryMerkleTree {
             }
 
             let (left_node_index, right_node_index) =
-                self.get_left_and_right_node_indices_from_index(current_index);
-            let left_node = self.tree[left_node_index];
-            let right_node = self.tree[right_node_index];
+                get_left_and_right_node_indices_from_index(current_index);
+            let left_node = self.tree.read(left_node_index);
+            let right_node = self.tree.read(right_node_index);
 
-            let parent_index = BinaryMerkleTree::get_parent_index(current_index);
+            let parent_index = get_parent_index(current_index);
+            let old_parent = self.tree.read(parent_index);
+            self.archived_nodes.push((version, parent_index, old_parent));
             let parent_hash = get_parent(&left_node, &right_node);
-            self.tree[parent_index] = parent_hash;
+            self.tree.set(parent_index, parent_hash);
             update_queue.push_back(parent_index);
         }
 
+        self.version_roots.push(self.root());
+
         Some(())
     }
 
-    fn get_sibling_index(index: usize) -> usize {
-        // Bit-wise XOR to get the sibling index
-        // Example: Sibling of index 4(0b100) is 5(0b101) and sibling of index 5(0b101) is 4(0b100)
-        index ^ 1
+    /// Reset a leaf back to the zero filler node and propagate the update to all ancestors,
+    /// so the slot is reported as empty again by `get_empty_leaves_indices`.
+    pub fn delete_leaf(&mut self, leaf_index: usize) {
+        let zero_node = blake3::Output::new([0; 16], 0);
+        self.update_leaf(leaf_index, zero_node);
+    }
+
+    /// Return the 0-indexed positions of every leaf that still holds the zero filler node,
+    /// i.e. every leaf slot that has never been assigned or has since been deleted.
+    pub fn get_empty_leaves_indices(&self) -> Vec<usize> {
+        let zero_chaining_value = blake3::Output::new([0; 16], 0).chaining_value();
+        let leaf_start_index = self.num_leaves();
+
+        (0..self.num_leaves())
+            .filter(|&leaf_index| {
+                self.tree.read(leaf_start_index + leaf_index).chaining_value() == zero_chaining_value
+            })
+            .collect()
+    }
+
+    /// Append a leaf after the last assigned leaf, growing the tree's capacity first if it is full.
+    /// Returns the 0-indexed leaf index the leaf was written to.
+    pub fn append_leaf(&mut self, leaf: blake3::Output) -> usize {
+        if self.next_index == self.num_leaves() {
+            self.grow();
+        }
+
+        let leaf_index = self.next_index;
+        self.update_leaf(leaf_index, leaf);
+        leaf_index
+    }
+
+    /// Double the tree's leaf capacity. Existing leaves are relocated into the new leaf region
+    /// and the rest of the new leaf region is filled with the zero filler node, then ancestors
+    /// along the paths of already-assigned leaves are recomputed.
+    pub fn grow(&mut self) {
+        let old_num_leaves = self.num_leaves();
+        let new_num_leaves = old_num_leaves * 2;
+        let zero_node = blake3::Output::new([0; 16], 0);
+
+        let old_leaves: Vec<blake3::Output> = (0..old_num_leaves)
+            .map(|leaf_index| self.tree.read(old_num_leaves + leaf_index))
+            .collect();
+
+        let new_leaf_start = new_num_leaves;
+        self.tree
+            .batch_set((0..4 * old_num_leaves).map(|index| (index, zero_node)));
+        self.tree.batch_set(
+            old_leaves
+                .into_iter()
+                .enumerate()
+                .map(|(offset, leaf)| (new_leaf_start + offset, leaf)),
+        );
+        self.num_leaves = new_num_leaves;
+
+        // Only the paths of leaves that have actually been assigned need their ancestors rebuilt;
+        // the rest of the tree is still the zero filler node all the way up.
+        for leaf_index in 0..self.next_index {
+            let mut current_index = leaf_index + new_leaf_start;
+            while current_index > 1 {
+                let parent_index = get_parent_index(current_index);
+                let (left_node_index, right_node_index) =
+                    get_left_and_right_node_indices_from_index(current_index);
+                let left_node = self.tree.read(left_node_index);
+                let right_node = self.tree.read(right_node_index);
+
+                self.tree
+                    .set(parent_index, get_parent(&left_node, &right_node));
+                current_index = parent_index;
+            }
+        }
+    }
+
+    /// Prove that the leaf at `leaf_index` (0-indexed) belongs to this tree.
+    /// Walks from the leaf up to the root, collecting the sibling hash needed at each level.
+    pub fn prove(&self, leaf_index: usize) -> Path {
+        let real_leaf_index = leaf_index + self.num_leaves();
+        let mut current_index = real_leaf_index;
+        let mut siblings = Vec::new();
+
+        while current_index > 1 {
+            let sibling_index = get_sibling_index(current_index);
+            siblings.push(self.tree.read(sibling_index));
+            current_index = get_parent_index(current_index);
+        }
+
+        Path {
+            index: real_leaf_index,
+            siblings,
+        }
+    }
+
+    /// Prove that the leaf at `leaf_index` (0-indexed) belonged to this tree as of `version`,
+    /// reading each sibling through `node_at_version` instead of the current tree. Returns `None`
+    /// if any sibling on the path was pruned before that version's state could be reconstructed;
+    /// verify the result against `root_at_version(version)` with the ordinary `verify`.
+    pub fn prove_at_version(&self, version: u64, leaf_index: usize) -> Option<Path> {
+        let real_leaf_index = leaf_index + self.num_leaves();
+        let mut current_index = real_leaf_index;
+        let mut siblings = Vec::new();
+
+        while current_index > 1 {
+            let sibling_index = get_sibling_index(current_index);
+            siblings.push(self.node_at_version(version, sibling_index)?);
+            current_index = get_parent_index(current_index);
+        }
+
+        Some(Path {
+            index: real_leaf_index,
+            siblings,
+        })
+    }
+
+    /// Verify that `leaf` belongs to the tree with the given `root`, using `path` as produced by `prove`.
+    /// Recomputes the root by repeatedly calling `get_parent`, ordering children via `is_left`.
+    pub fn verify(root: &blake3::Output, leaf: &blake3::Output, path: &Path) -> bool {
+        let mut current_hash = *leaf;
+        let mut current_index = path.index;
+
+        for sibling in &path.siblings {
+            current_hash = if is_left(current_index) {
+                get_parent(&current_hash, sibling)
+            } else {
+                get_parent(sibling, &current_hash)
+            };
+            current_index = get_parent_index(current_index);
+        }
+
+        current_hash.chaining_value() == root.chaining_value()
+    }
+
+    /// Prove that every leaf in `sorted_leaf_indices` (0-indexed, ascending) belongs to this tree.
+    /// Shared ancestors are deduplicated: whenever two proven nodes are siblings, neither sibling
+    /// hash is stored since their parent is derivable directly from the two proven nodes.
+    pub fn prove_many(&self, sorted_leaf_indices: &[usize]) -> BatchPath {
+        if sorted_leaf_indices.is_empty() {
+            return BatchPath {
+                indices: Vec::new(),
+                siblings: Vec::new(),
+            };
+        }
+
+        let leaf_offset = self.num_leaves();
+        let proven_indices: Vec<usize> = sorted_leaf_indices
+            .iter()
+            .map(|&leaf_index| leaf_index + leaf_offset)
+            .collect();
+
+        let mut current_level = proven_indices.clone();
+        let mut siblings = Vec::new();
+
+        while current_level != [1] {
+            let mut next_level = Vec::new();
+            let mut iter = current_level.iter().peekable();
+
+            while let Some(&index) = iter.next() {
+                let sibling_index = get_sibling_index(index);
+                if iter.peek() == Some(&&sibling_index) {
+                    // The sibling is also being proven, so its hash is derivable and not stored.
+                    iter.next();
+                } else {
+                    siblings.push(self.tree.read(sibling_index));
+                }
+
+                let parent_index = get_parent_index(index);
+                if next_level.last() != Some(&parent_index) {
+                    next_level.push(parent_index);
+                }
+            }
+
+            current_level = next_level;
+        }
+
+        BatchPath {
+            indices: proven_indices,
+            siblings,
+        }
+    }
+
+    /// Verify a batch of `leaves` (in the same order as the indices used to build `path`)
+    /// against `root`, reconstructing the frontier level by level and consuming stored
+    /// sibling hashes only where a sibling is not itself part of the proven set.
+    pub fn verify_batch(root: &blake3::Output, leaves: &[blake3::Output], path: &BatchPath) -> bool {
+        if path.indices.len() != leaves.len() {
+            return false;
+        }
+        if path.indices.is_empty() {
+            // Nothing was claimed to be included, so there is nothing to refute.
+            return true;
+        }
+
+        let mut current_level: Vec<(usize, blake3::Output)> = path
+            .indices
+            .iter()
+            .copied()
+            .zip(leaves.iter().copied())
+            .collect();
+        let mut sibling_iter = path.siblings.iter();
+
+        while !(current_level.len() == 1 && current_level[0].0 == 1) {
+            let mut next_level: Vec<(usize, blake3::Output)> = Vec::new();
+            let mut iter = current_level.into_iter().peekable();
+
+            while let Some((index, hash)) = iter.next() {
+                let sibling_index = get_sibling_index(index);
+                let parent_index = get_parent_index(index);
+
+                let parent_hash = match iter.peek().copied() {
+                    Some((next_index, next_hash)) if next_index == sibling_index => {
+                        iter.next();
+                        if is_left(index) {
+                            get_parent(&hash, &next_hash)
+                        } else {
+                            get_parent(&next_hash, &hash)
+                        }
+                    }
+                    _ => {
+                        let sibling_hash = match sibling_iter.next() {
+                            Some(hash) => hash,
+                            None => return false,
+                        };
+                        if is_left(index) {
+                            get_parent(&hash, sibling_hash)
+                        } else {
+                            get_parent(sibling_hash, &hash)
+                        }
+                    }
+                };
+
+                if next_level.last().map(|&(i, _)| i) != Some(parent_index) {
+                    next_level.push((parent_index, parent_hash));
+                }
+            }
+
+            current_level = next_level;
+        }
+
+        current_level[0].1.chaining_value() == root.chaining_value()
+    }
+}
+
+/// The largest power of two that is `<= index`. Used to find a node's depth within its subtree
+/// when reindexing nodes across trees of different sizes (see `BinaryMerkleTree::merge`).
+fn highest_power_of_two_leq(index: usize) -> usize {
+    1usize << (usize::BITS - 1 - index.leading_zeros())
+}
+
+impl BinaryMerkleTree<Vec<blake3::Output>> {
+    /// Merge `self` and `other` into a combined tree whose leaves are `self`'s leaves followed
+    /// by `other`'s leaves.
+    ///
+    /// When both trees are full power-of-two trees of equal size, their existing roots become the
+    /// two children of a single new root, so the merged interior is assembled in linear time by
+    /// copying each input's node array into the correct half of a doubled array and hashing only
+    /// the newly created spine above them; the result's `num_leaves` is the next power of two of
+    /// the combined leaf count. Otherwise falls back to appending each of `other`'s leaves onto
+    /// `self` one at a time, recomputing only the ancestor paths each append affects; the result
+    /// keeps whichever capacity that append sequence ends on, which may be larger than the next
+    /// power of two of the combined count if `self` already had spare capacity.
+    pub fn merge(
+        self,
+        other: BinaryMerkleTree<Vec<blake3::Output>>,
+    ) -> BinaryMerkleTree<Vec<blake3::Output>> {
+        let both_full = self.next_index == self.num_leaves() && other.next_index == other.num_leaves();
+
+        if both_full && self.num_leaves() == other.num_leaves() {
+            return Self::merge_equal_full_trees(self, other);
+        }
+
+        let mut merged = self;
+        for leaf_index in 0..other.next_index {
+            merged.append_leaf(other.tree[leaf_index + other.num_leaves()]);
+        }
+        merged
+    }
+
+    fn merge_equal_full_trees(
+        left: BinaryMerkleTree<Vec<blake3::Output>>,
+        right: BinaryMerkleTree<Vec<blake3::Output>>,
+    ) -> BinaryMerkleTree<Vec<blake3::Output>> {
+        let n = left.num_leaves();
+        let new_num_leaves = 2 * n;
+        let mut merged = Self::new_empty(new_num_leaves as u64);
+
+        // `left`'s nodes become the subtree rooted at index 2 (the new root's left child) and
+        // `right`'s nodes become the subtree rooted at index 3, by reindexing each old index `i`
+        // to `child_root_index * depth_base + (i - depth_base)`, where `depth_base` is the
+        // largest power of two `<= i`, i.e. the first index at `i`'s depth.
+        for (input, child_root_index) in [(left, 2usize), (right, 3usize)] {
+            for old_index in 1..2 * n {
+                let depth_base = highest_power_of_two_leq(old_index);
+                let new_index = child_root_index * depth_base + (old_index - depth_base);
+                merged.tree[new_index] = input.tree[old_index];
+            }
+        }
+
+        merged.tree[1] = get_parent(&merged.tree[2], &merged.tree[3]);
+        merged.next_index = new_num_leaves;
+        merged
+    }
+}
+
+/// An inclusion proof for a single leaf: the sibling hash at every level from the leaf up to the root.
+#[derive(Debug, Clone)]
+pub struct Path {
+    /// The leaf's position within the 1-indexed tree array.
+    index: usize,
+    /// Sibling hashes, ordered from the leaf's sibling up to the root's child.
+    siblings: Vec<blake3::Output>,
+}
+
+/// A compact inclusion proof for several leaves at once. Sibling hashes that are themselves
+/// part of the proven set are omitted, since their parent is derivable from the proven nodes.
+#[derive(Debug, Clone)]
+pub struct BatchPath {
+    /// The proven leaves' positions within the 1-indexed tree array, ascending.
+    indices: Vec<usize>,
+    /// Sibling hashes needed to reconstruct the root, level by level.
+    siblings: Vec<blake3::Output>,
+}
+
+/// Binary merkle tree built over exactly N leaves, without padding to a power of two.
+/// Nodes are stored in a `2N`-sized array: leaves occupy indices `N..2N` and internal node `i`
+/// is the parent of `2i` and `2i+1`. This is the same 1-indexed index math as `BinaryMerkleTree`
+/// (`get_parent_index`, `get_sibling_index`, `is_left`), but the tree is balanced for any leaf
+/// count instead of only powers of two, so no space is wasted on zero padding.
+#[derive(Debug, Clone)]
+pub struct CompleteBinaryMerkleTree {
+    pub tree: Vec<blake3::Output>,
+}
+
+impl CompleteBinaryMerkleTree {
+    pub fn new_from_leaves(leaves: Vec<blake3::Output>) -> CompleteBinaryMerkleTree {
+        let number_of_leaves = leaves.len();
+        let mut tree = vec![blake3::Output::new([0; 16], 0); 2 * number_of_leaves];
+        tree[number_of_leaves..].clone_from_slice(&leaves);
+
+        let mut result = CompleteBinaryMerkleTree { tree };
+        // Internal nodes are computed bottom-up; node `i`'s children `2i` and `2i+1` are always
+        // already in place by the time we reach `i`.
+        for index in (1..number_of_leaves).rev() {
+            let left = result.tree[2 * index];
+            let right = result.tree[2 * index + 1];
+            result.tree[index] = get_parent(&left, &right);
+        }
+        result
+    }
+
+    pub fn root(&self) -> blake3::Output {
+        self.tree[1]
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        self.tree.len() / 2
+    }
+
+    /// Update a leaf and propagate updates to all ancestors.
+    /// Leaf_index input should be 0-indexed where the first leaf would be entered as index 0
+    pub fn update_leaf(&mut self, leaf_index: usize, leaf: blake3::Output) {
+        let real_leaf_index = leaf_index + self.num_leaves();
+        self.tree[real_leaf_index] = leaf;
+
+        let mut current_index = real_leaf_index;
+        while current_index > 1 {
+            let parent_index = get_parent_index(current_index);
+            let sibling_index = get_sibling_index(current_index);
+
+            let (left_node, right_node) = if is_left(current_index) {
+                (self.tree[current_index], self.tree[sibling_index])
+            } else {
+                (self.tree[sibling_index], self.tree[current_index])
+            };
+
+            self.tree[parent_index] = get_parent(&left_node, &right_node);
+            current_index = parent_index;
+        }
     }
 
-    fn is_left(index: usize) -> bool {
-        // All left-children have an even node index
-        index % 2 == 0
+    /// Prove that the leaf at `leaf_index` (0-indexed) belongs to this tree.
+    /// Verify with the same `BinaryMerkleTree::verify` used for padded trees, since the
+    /// index math and `Path` representation are identical between the two modes.
+    pub fn prove(&self, leaf_index: usize) -> Path {
+        let real_leaf_index = leaf_index + self.num_leaves();
+        let mut current_index = real_leaf_index;
+        let mut siblings = Vec::new();
+
+        while current_index > 1 {
+            let sibling_index = get_sibling_index(current_index);
+            siblings.push(self.tree[sibling_index]);
+            current_index = get_parent_index(current_index);
+        }
+
+        Path {
+            index: real_leaf_index,
+            siblings,
+        }
     }
+}
+
+/// Parallel tree construction, gated behind the `parallel` feature since it pulls in rayon.
+/// Only implemented for the default `Vec`-backed tree: partitioning relies on slicing the
+/// backing store directly, which isn't meaningful for an arbitrary `NodeStore`.
+#[cfg(feature = "parallel")]
+mod parallel_build {
+    use super::*;
+    use rayon::prelude::*;
+
+    impl BinaryMerkleTree<Vec<blake3::Output>> {
+        /// Build the tree the same way as `new_from_leaves`, but hash each partition's interior
+        /// levels in parallel with rayon. A subtree rooted at index `t` covers a contiguous leaf
+        /// range, so siblings never cross a partition boundary below the partition root and no
+        /// synchronization is needed within a partition. The handful of levels above the
+        /// partition roots are then combined sequentially.
+        pub fn new_from_leaves_parallel(leaves: Vec<blake3::Output>) -> BinaryMerkleTree<Vec<blake3::Output>> {
+            let number_of_assigned_leaves = leaves.len();
+            let number_of_leaves = leaves.len().next_power_of_two();
+            let mut tree = Self::new_empty(number_of_leaves as u64);
+
+            if leaves.len() <= 1 {
+                tree.create_tree_from_leaves(leaves);
+                tree.next_index = number_of_assigned_leaves;
+                return tree;
+            }
+
+            let leaf_start = tree.get_tree_length() / 2 + 1;
+            let leaf_write_start = 2 * number_of_leaves - leaves.len();
+            tree.tree[leaf_write_start..leaf_write_start + leaves.len()].copy_from_slice(&leaves);
+
+            let num_partitions = rayon::current_num_threads()
+                .next_power_of_two()
+                .min(number_of_leaves);
+            let partition_size = number_of_leaves / num_partitions;
+
+            let partition_results: Vec<(usize, blake3::Output, Vec<(usize, blake3::Output)>)> =
+                (0..num_partitions)
+                    .into_par_iter()
+                    .map(|partition_index| {
+                        let partition_leaf_start = leaf_start + partition_index * partition_size;
+                        let partition_leaves: Vec<blake3::Output> = (0..partition_size)
+                            .map(|offset| tree.tree[partition_leaf_start + offset])
+                            .collect();
+                        hash_partition(partition_leaf_start, &partition_leaves)
+                    })
+                    .collect();
+
+            for (_, _, nodes) in &partition_results {
+                for &(index, value) in nodes {
+                    tree.tree[index] = value;
+                }
+            }
 
-    /// Given an index of the current node, identify its direct sibling,
-    /// identify which node is left, which is right, and return them.
-    fn get_left_and_right_node_indices_from_index(&self, current_index: usize) -> (usize, usize) {
-        let sibling_index = BinaryMerkleTree::get_sibling_index(current_index);
+            // Combine the partition roots sequentially up to the global root.
+            let mut combine_queue: VecDeque<(blake3::Output, usize)> = partition_results
+                .into_iter()
+                .map(|(root_index, root_hash, _)| (root_hash, root_index))
+                .collect();
+            while combine_queue.len() > 1 {
+                let (left_child, left_index) = combine_queue.pop_front().unwrap();
+                let (right_child, _right_index) = combine_queue.pop_front().unwrap();
+                let parent_index = get_parent_index(left_index);
 
-        // Use boolean indexing to avoid if statement branching
-        let node_pair = [current_index, sibling_index]; // Stack allocation
+                let parent_hash = get_parent(&left_child, &right_child);
+                tree.tree[parent_index] = parent_hash;
+                combine_queue.push_back((parent_hash, parent_index));
+            }
+
+            // Leaves are written right-aligned, so the last leaf slot is the highest one assigned.
+            tree.next_index = number_of_leaves;
+            tree
+        }
+    }
+
+    /// Hash one partition's interior bottom-up, returning its subtree root (index and hash)
+    /// along with every interior node value computed along the way.
+    fn hash_partition(
+        leaf_start: usize,
+        leaves: &[blake3::Output],
+    ) -> (usize, blake3::Output, Vec<(usize, blake3::Output)>) {
+        let mut nodes = Vec::new();
+        let mut hash_queue: VecDeque<(blake3::Output, usize)> = leaves
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(offset, leaf)| (leaf, leaf_start + offset))
+            .collect();
 
-        // If the sibling is the left child, is_left returns 1 and gets the sibling
-        // If the sibling is the right child, is_left returns 0 and gets the node to update (the left child)
-        let left_node_index = node_pair[BinaryMerkleTree::is_left(sibling_index) as usize];
+        while hash_queue.len() > 1 {
+            let (left_child, left_index) = hash_queue.pop_front().unwrap();
+            let (right_child, _right_index) = hash_queue.pop_front().unwrap();
+            let parent_index = get_parent_index(left_index);
 
-        // If the node to update is the left child, is_left returns 1 and gets the sibling (the right child)
-        // If the node to update is the right child, is_left returns 0 and gets the node to update
-        let right_node_index = node_pair[BinaryMerkleTree::is_left(current_index) as usize];
+            let parent_hash = get_parent(&left_child, &right_child);
+            nodes.push((parent_index, parent_hash));
+            hash_queue.push_back((parent_hash, parent_index));
+        }
 
-        (left_node_index, right_node_index)
+        let (root_hash, root_index) = hash_queue.pop_front().unwrap();
+        (root_index, root_hash, nodes)
     }
 }
 
@@ -223,7 +858,7 @@ mod tests {
             blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2),
             blake3::Output::new(unsafe { std::mem::transmute([b'S'; 64]) }, 3),
         ];
-        let exp_tree = BinaryMerkleTree::new_from_leaves(exp_leaves);
+        let exp_tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(exp_leaves);
 
         let act_leaves: Vec<blake3::Output> = vec![
             blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0),
@@ -231,7 +866,7 @@ mod tests {
             blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2),
             blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3),
         ];
-        let mut act_tree = BinaryMerkleTree::new_from_leaves(act_leaves);
+        let mut act_tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(act_leaves);
 
         let index0: usize = 0;
         act_tree.update_leaf(
@@ -252,6 +887,65 @@ mod tests {
         assert_eq!(exp_out, act_out);
     }
 
+    #[test]
+    fn test_prove_and_verify() {
+        let leaves: Vec<blake3::Output> = vec![
+            blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0),
+            blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1),
+            blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2),
+            blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3),
+        ];
+        let tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(leaves.clone());
+
+        let path = tree.prove(1);
+        assert!(BinaryMerkleTree::<Vec<blake3::Output>>::verify(
+            &tree.root(),
+            &leaves[1],
+            &path
+        ));
+
+        let wrong_leaf = blake3::Output::new(unsafe { std::mem::transmute([b'Z'; 64]) }, 1);
+        assert!(!BinaryMerkleTree::<Vec<blake3::Output>>::verify(
+            &tree.root(),
+            &wrong_leaf,
+            &path
+        ));
+    }
+
+    #[test]
+    fn test_prove_many_and_verify_batch() {
+        let leaves: Vec<blake3::Output> = vec![
+            blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0),
+            blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1),
+            blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2),
+            blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3),
+        ];
+        let tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(leaves.clone());
+
+        let batch_path = tree.prove_many(&[0, 1, 3]);
+        let batch_leaves = [leaves[0], leaves[1], leaves[3]];
+        assert!(BinaryMerkleTree::<Vec<blake3::Output>>::verify_batch(
+            &tree.root(),
+            &batch_leaves,
+            &batch_path
+        ));
+
+        let mut tampered_leaves = batch_leaves;
+        tampered_leaves[2] = blake3::Output::new(unsafe { std::mem::transmute([b'Z'; 64]) }, 3);
+        assert!(!BinaryMerkleTree::<Vec<blake3::Output>>::verify_batch(
+            &tree.root(),
+            &tampered_leaves,
+            &batch_path
+        ));
+
+        let empty_path = tree.prove_many(&[]);
+        assert!(BinaryMerkleTree::<Vec<blake3::Output>>::verify_batch(
+            &tree.root(),
+            &[],
+            &empty_path
+        ));
+    }
+
     #[test]
     fn test_blake3_correctness() {
         let exp_leaves = &[[b'A'; 64], [b'B'; 64], [b'C'; 64], [b'D'; 64]].concat();
@@ -267,44 +961,294 @@ mod tests {
             blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2),
             blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3),
         ];
-        let act_tree = BinaryMerkleTree::new_from_leaves(act_leaves);
+        let act_tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(act_leaves);
 
         let mut act = [0u8; 32];
         act_tree.root().root_output_bytes(&mut act);
         assert_eq!(exp_hash, act);
     }
 
-    // #[test]
-    // fn test_bulk_update_performance() {
-    //     let num_updates = 10000;
-    //     let leaves: Vec<blake3::Output> = (0..num_updates)
-    //         .map(|i| blake3::Output::new(unsafe { std::mem::transmute([i as u8; 64]) }, i as u64))
-    //         .collect();
-
-    //     // Measure time for bulk hashing using blake3 hasher
-    //     let start = Instant::now();
-    //     let mut b3hasher = blake3::Hasher::new();
-    //     for leaf in &leaves {
-    //         let new: [u8; 32] = unsafe { std::mem::transmute( leaf.chaining_value() ) };
-    //         b3hasher.update(&new);
-    //     }
-    //     let mut bulk_hash = [0u8; 32];
-    //     b3hasher.finalize(&mut bulk_hash);
-    //     let bulk_duration = start.elapsed();
-
-    //     // Measure time for incremental updates using BinaryMerkleTree
-    //     let mut tree = BinaryMerkleTree::new_from_leaves(leaves.clone());
-    //     let start = Instant::now();
-    //     for (i, leaf) in leaves.iter().enumerate() {
-    //         tree.update_leaf(i, *leaf);
-    //     }
-    //     let incremental_duration = start.elapsed();
-
-    //     assert!(incremental_duration < bulk_duration);
-
-    //     // Ensure the root hash is the same
-    //     let mut tree_root_hash = [0u8; 32];
-    //     tree.root().root_output_bytes(&mut tree_root_hash);
-    //     assert_eq!(bulk_hash, tree_root_hash);
-    // }
+    #[test]
+    fn test_new_from_leaves_with_non_power_of_two_leaf_count() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+        let zero_node = blake3::Output::new([0; 16], 0);
+
+        // 3 leaves pads to capacity 4, exercising the padded (non-power-of-two input) path.
+        let tree: BinaryMerkleTree =
+            BinaryMerkleTree::new_from_leaves(vec![leaf_a, leaf_b, leaf_c]);
+
+        // Leaves are written right-aligned within the leaf region, so the layout is
+        // [zero, A, B, C], not [A, B, C, zero]; compute the expected root from that layout
+        // directly rather than through another tree-building path.
+        let left = get_parent(&zero_node, &leaf_a);
+        let right = get_parent(&leaf_b, &leaf_c);
+        let expected_root = get_parent(&left, &right);
+
+        assert_eq!(
+            tree.root().chaining_value(),
+            expected_root.chaining_value()
+        );
+    }
+
+    #[test]
+    fn test_append_leaf_grows_past_initial_capacity() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+
+        let mut tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(vec![leaf_a, leaf_b]);
+        assert_eq!(tree.num_leaves(), 2);
+
+        // The tree is already full at 2 leaves, so this append must grow capacity to 4 first.
+        let index_c = tree.append_leaf(leaf_c);
+        assert_eq!(index_c, 2);
+        assert_eq!(tree.num_leaves(), 4);
+
+        // Build the expected post-grow tree leaf by leaf, since its root should match a tree
+        // that was simply assigned the same three leaves directly.
+        let mut exp_tree: BinaryMerkleTree = BinaryMerkleTree::new_empty(4);
+        exp_tree.update_leaf(0, leaf_a);
+        exp_tree.update_leaf(1, leaf_b);
+        exp_tree.update_leaf(2, leaf_c);
+
+        let mut exp_out = [0u8; 32];
+        let mut act_out = [0u8; 32];
+        exp_tree.root().root_output_bytes(&mut exp_out);
+        tree.root().root_output_bytes(&mut act_out);
+        assert_eq!(exp_out, act_out);
+    }
+
+    #[test]
+    fn test_delete_leaf_and_empty_leaves_indices() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+        let leaf_d = blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3);
+
+        let mut tree: BinaryMerkleTree =
+            BinaryMerkleTree::new_from_leaves(vec![leaf_a, leaf_b, leaf_c, leaf_d]);
+        assert!(tree.get_empty_leaves_indices().is_empty());
+
+        tree.delete_leaf(1);
+        assert_eq!(tree.get_empty_leaves_indices(), vec![1]);
+
+        // The deleted slot's root should match a tree that had a zero leaf there all along.
+        let zero_node = blake3::Output::new([0; 16], 0);
+        let mut exp_tree: BinaryMerkleTree = BinaryMerkleTree::new_empty(4);
+        exp_tree.update_leaf(0, leaf_a);
+        exp_tree.update_leaf(1, zero_node);
+        exp_tree.update_leaf(2, leaf_c);
+        exp_tree.update_leaf(3, leaf_d);
+
+        let mut exp_out = [0u8; 32];
+        let mut act_out = [0u8; 32];
+        exp_tree.root().root_output_bytes(&mut exp_out);
+        tree.root().root_output_bytes(&mut act_out);
+        assert_eq!(exp_out, act_out);
+
+        // Recycling the deleted slot clears it from the empty list again.
+        tree.update_leaf(1, leaf_b);
+        assert!(tree.get_empty_leaves_indices().is_empty());
+    }
+
+    #[test]
+    fn test_complete_binary_merkle_tree_correctness_and_proofs() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+        let leaf_x = blake3::Output::new(unsafe { std::mem::transmute([b'X'; 64]) }, 2);
+
+        let exp_tree =
+            CompleteBinaryMerkleTree::new_from_leaves(vec![leaf_a, leaf_b, leaf_x]);
+
+        let mut act_tree =
+            CompleteBinaryMerkleTree::new_from_leaves(vec![leaf_a, leaf_b, leaf_c]);
+        assert_eq!(act_tree.num_leaves(), 3);
+        act_tree.update_leaf(2, leaf_x);
+
+        let mut exp_out = [0u8; 32];
+        let mut act_out = [0u8; 32];
+        exp_tree.root().root_output_bytes(&mut exp_out);
+        act_tree.root().root_output_bytes(&mut act_out);
+        assert_eq!(exp_out, act_out);
+
+        let path = act_tree.prove(2);
+        assert!(BinaryMerkleTree::<Vec<blake3::Output>>::verify(
+            &act_tree.root(),
+            &leaf_x,
+            &path
+        ));
+        assert!(!BinaryMerkleTree::<Vec<blake3::Output>>::verify(
+            &act_tree.root(),
+            &leaf_c,
+            &path
+        ));
+    }
+
+    #[test]
+    fn test_versioned_roots_and_pruned_history() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+        let leaf_d = blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3);
+        let leaf_x = blake3::Output::new(unsafe { std::mem::transmute([b'X'; 64]) }, 1);
+
+        let mut tree: BinaryMerkleTree = BinaryMerkleTree::new_empty(4);
+        tree.bulk_insert_leaves(0..4, vec![leaf_a, leaf_b, leaf_c, leaf_d].into_iter())
+            .unwrap();
+        assert_eq!(tree.current_version(), 1);
+        let v0_root = tree.root_at_version(0).unwrap();
+        assert_eq!(v0_root.chaining_value(), tree.root().chaining_value());
+
+        // Leaf 1's version-0 inclusion is provable against the version-0 root right after the
+        // commit that recorded it.
+        let v0_path = tree.prove_at_version(0, 1).unwrap();
+        assert!(BinaryMerkleTree::<Vec<blake3::Output>>::verify(
+            &v0_root, &leaf_b, &v0_path
+        ));
+
+        tree.bulk_insert_leaves([1].into_iter(), vec![leaf_x].into_iter())
+            .unwrap();
+        assert_eq!(tree.current_version(), 2);
+        let v1_root = tree.root_at_version(1).unwrap();
+        assert_ne!(v0_root.chaining_value(), v1_root.chaining_value());
+
+        // Leaf 1's version-0 value is still provable against the version-0 root immediately
+        // after the commit that overwrote it, even though the tree's current value moved on.
+        let v0_path_again = tree.prove_at_version(0, 1).unwrap();
+        assert!(BinaryMerkleTree::<Vec<blake3::Output>>::verify(
+            &v0_root, &leaf_b, &v0_path_again
+        ));
+
+        // Pruning up to the current version drops the archived values needed to reconstruct
+        // version 0's nodes, so it's no longer provable, even though its root is still on record.
+        tree.prune(2);
+        assert!(tree.prove_at_version(0, 1).is_none());
+        assert_eq!(
+            tree.root_at_version(0).unwrap().chaining_value(),
+            v0_root.chaining_value()
+        );
+    }
+
+    #[test]
+    fn test_merge_equal_full_trees_fast_path() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+        let leaf_d = blake3::Output::new(unsafe { std::mem::transmute([b'D'; 64]) }, 3);
+
+        let mut left: BinaryMerkleTree = BinaryMerkleTree::new_empty(2);
+        left.update_leaf(0, leaf_a);
+        left.update_leaf(1, leaf_b);
+
+        let mut right: BinaryMerkleTree = BinaryMerkleTree::new_empty(2);
+        right.update_leaf(0, leaf_c);
+        right.update_leaf(1, leaf_d);
+
+        let merged = left.merge(right);
+
+        let mut expected: BinaryMerkleTree = BinaryMerkleTree::new_empty(4);
+        expected.update_leaf(0, leaf_a);
+        expected.update_leaf(1, leaf_b);
+        expected.update_leaf(2, leaf_c);
+        expected.update_leaf(3, leaf_d);
+
+        assert_eq!(
+            merged.root().chaining_value(),
+            expected.root().chaining_value()
+        );
+    }
+
+    #[test]
+    fn test_merge_falls_back_to_append_for_unequal_or_partial_trees() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+
+        let mut left: BinaryMerkleTree = BinaryMerkleTree::new_empty(2);
+        left.update_leaf(0, leaf_a);
+        left.update_leaf(1, leaf_b);
+
+        // `right` is not full (capacity 4, only one leaf assigned), so the equal-full-trees fast
+        // path does not apply and `merge` falls back to appending `right`'s assigned leaves.
+        let mut right: BinaryMerkleTree = BinaryMerkleTree::new_empty(4);
+        right.update_leaf(0, leaf_c);
+
+        let merged = left.merge(right);
+
+        let mut expected: BinaryMerkleTree = BinaryMerkleTree::new_empty(4);
+        expected.update_leaf(0, leaf_a);
+        expected.update_leaf(1, leaf_b);
+        expected.update_leaf(2, leaf_c);
+
+        assert_eq!(
+            merged.root().chaining_value(),
+            expected.root().chaining_value()
+        );
+    }
+
+    #[test]
+    fn test_merge_fallback_keeps_spare_capacity_from_self() {
+        let leaf_a = blake3::Output::new(unsafe { std::mem::transmute([b'A'; 64]) }, 0);
+        let leaf_b = blake3::Output::new(unsafe { std::mem::transmute([b'B'; 64]) }, 1);
+        let leaf_c = blake3::Output::new(unsafe { std::mem::transmute([b'C'; 64]) }, 2);
+
+        // `left` already has capacity 8 with only 1 leaf assigned, far more spare room than the
+        // combined leaf count needs. The fallback path appends onto it in place rather than
+        // rebuilding at a minimal capacity, so the merged tree keeps `left`'s capacity of 8
+        // instead of shrinking to `next_power_of_two(3) == 4`.
+        let mut left: BinaryMerkleTree = BinaryMerkleTree::new_empty(8);
+        left.update_leaf(0, leaf_a);
+
+        let mut right: BinaryMerkleTree = BinaryMerkleTree::new_empty(2);
+        right.update_leaf(0, leaf_b);
+        right.update_leaf(1, leaf_c);
+
+        let merged = left.merge(right);
+
+        assert_eq!(merged.num_leaves(), 8);
+
+        let mut expected: BinaryMerkleTree = BinaryMerkleTree::new_empty(8);
+        expected.update_leaf(0, leaf_a);
+        expected.update_leaf(1, leaf_b);
+        expected.update_leaf(2, leaf_c);
+
+        assert_eq!(
+            merged.root().chaining_value(),
+            expected.root().chaining_value()
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_build_matches_serial_and_logs_timings() {
+        let num_leaves = 1 << 14;
+        let leaves: Vec<blake3::Output> = (0..num_leaves)
+            .map(|i| blake3::Output::new(unsafe { std::mem::transmute([i as u8; 64]) }, i as u64))
+            .collect();
+
+        let start = Instant::now();
+        let serial_tree: BinaryMerkleTree = BinaryMerkleTree::new_from_leaves(leaves.clone());
+        let serial_duration = start.elapsed();
+
+        let start = Instant::now();
+        let parallel_tree = BinaryMerkleTree::new_from_leaves_parallel(leaves);
+        let parallel_duration = start.elapsed();
+
+        // Timing comparisons are too noisy on a shared/CI machine to assert on directly; log them
+        // for eyeballing and only assert the thing that actually has to hold, root equality.
+        println!(
+            "serial build: {:?}, parallel build: {:?}",
+            serial_duration, parallel_duration
+        );
+
+        let mut serial_root = [0u8; 32];
+        let mut parallel_root = [0u8; 32];
+        serial_tree.root().root_output_bytes(&mut serial_root);
+        parallel_tree.root().root_output_bytes(&mut parallel_root);
+
+        assert_eq!(serial_root, parallel_root);
+    }
 }